@@ -0,0 +1,164 @@
+// Copyright (c) 2017-2018 ETH Zurich
+// Fabian Schuiki <fschuiki@iis.ee.ethz.ch>
+
+//! Package manifest and lock file configuration.
+
+#![deny(missing_docs)]
+
+use std::path::PathBuf;
+use std::collections::HashMap;
+use std::fmt;
+
+use semver;
+use serde::de::{self, Deserialize, Deserializer};
+
+/// The tool-wide configuration.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The directory where git/hg databases, checkouts, and the registry
+    /// cache are kept.
+    pub database: PathBuf,
+}
+
+/// A package manifest, as parsed from a `Bender.yml`.
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    /// The package described by this manifest.
+    pub package: Package,
+    /// The dependencies of this package.
+    pub dependencies: HashMap<String, Dependency>,
+}
+
+/// The `package` section of a manifest.
+#[derive(Debug, Clone)]
+pub struct Package {
+    /// The name of the package.
+    pub name: String,
+}
+
+/// A dependency as declared in a manifest.
+///
+/// Dependencies are given either as a bare version requirement, or as a
+/// table naming exactly one source (`path`, `git`, `hg`) together with
+/// however that source picks a point in history (`version` or `rev`).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Dependency {
+    /// A dependency resolved against the registry, constrained by version.
+    Version(semver::VersionReq),
+    /// A dependency located at a fixed path. No version resolution is
+    /// performed.
+    Path(PathBuf),
+    /// A git dependency pinned to an exact revision, with its submodule
+    /// checkout opt-out.
+    GitRevision(String, String, bool),
+    /// A git dependency constrained by version, with its submodule checkout
+    /// opt-out.
+    GitVersion(String, semver::VersionReq, bool),
+    /// A mercurial dependency pinned to an exact revision.
+    HgRevision(String, String),
+    /// A mercurial dependency constrained by version.
+    HgVersion(String, semver::VersionReq),
+}
+
+/// Whether a git dependency's submodules are checked out recursively by
+/// default. Hardware IP repositories vendor sub-IP this way far more often
+/// than not, so the default favors completeness over speed.
+fn default_submodules() -> bool {
+    true
+}
+
+impl<'de> Deserialize<'de> for Dependency {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Version(String),
+            Table(RawTable),
+        }
+
+        #[derive(Deserialize)]
+        struct RawTable {
+            version: Option<String>,
+            path: Option<PathBuf>,
+            git: Option<String>,
+            hg: Option<String>,
+            rev: Option<String>,
+            #[serde(default = "default_submodules")]
+            submodules: bool,
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Version(v) => {
+                let req = semver::VersionReq::parse(&v).map_err(de::Error::custom)?;
+                Ok(Dependency::Version(req))
+            }
+            Raw::Table(t) => {
+                match (t.path, t.git, t.hg, t.rev, t.version) {
+                    (Some(p), None, None, None, None) => Ok(Dependency::Path(p)),
+                    (None, Some(g), None, Some(rev), None) => Ok(Dependency::GitRevision(g, rev, t.submodules)),
+                    (None, Some(g), None, None, Some(v)) => {
+                        let req = semver::VersionReq::parse(&v).map_err(de::Error::custom)?;
+                        Ok(Dependency::GitVersion(g, req, t.submodules))
+                    }
+                    (None, None, Some(h), Some(rev), None) => Ok(Dependency::HgRevision(h, rev)),
+                    (None, None, Some(h), None, Some(v)) => {
+                        let req = semver::VersionReq::parse(&v).map_err(de::Error::custom)?;
+                        Ok(Dependency::HgVersion(h, req))
+                    }
+                    _ => Err(de::Error::custom(
+                        "dependency must specify exactly one of `path`, `git`, or `hg`, the \
+                         latter two together with exactly one of `rev`/`version`"
+                    )),
+                }
+            }
+        }
+    }
+}
+
+/// A lock file, as parsed from a `Bender.lock`.
+#[derive(Debug, Clone)]
+pub struct Locked {
+    /// The locked dependency packages, by name.
+    pub packages: HashMap<String, LockedPackage>,
+}
+
+/// A single locked dependency.
+#[derive(Debug, Clone)]
+pub struct LockedPackage {
+    /// Where this dependency was resolved from.
+    pub source: LockedSource,
+    /// The exact revision that was picked, if the source has one.
+    pub revision: Option<String>,
+    /// The exact version that was picked, if the source has one.
+    pub version: Option<String>,
+}
+
+/// Where a locked dependency was resolved from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LockedSource {
+    /// The dependency is located at a fixed path.
+    Path(PathBuf),
+    /// The dependency was resolved from a git url, with its submodule
+    /// checkout opt-out.
+    Git(String, bool),
+    /// The dependency was resolved from a mercurial url.
+    Hg(String),
+    /// The dependency was resolved from the registry, at the given version.
+    Registry(Option<String>),
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Dependency::Version(ref v) => write!(f, "{}", v),
+            Dependency::Path(ref p) => write!(f, "{}", p.display()),
+            Dependency::GitRevision(ref g, ref r, _) => write!(f, "{} rev {}", g, r),
+            Dependency::GitVersion(ref g, ref v, _) => write!(f, "{} version {}", g, v),
+            Dependency::HgRevision(ref h, ref r) => write!(f, "{} rev {}", h, r),
+            Dependency::HgVersion(ref h, ref v) => write!(f, "{} version {}", h, v),
+        }
+    }
+}
@@ -35,6 +35,13 @@ pub struct Session<'ctx> {
     /// The arenas into which we allocate various things that need to live as
     /// long as the session.
     arenas: &'ctx SessionArenas,
+    /// Whether network access is disabled.
+    ///
+    /// In this mode nothing is fetched or downloaded. Databases and
+    /// archives that already exist on disk are used as they are, without
+    /// being updated; a dependency, revision or archive that is not present
+    /// locally fails with an error instead.
+    pub frozen: bool,
     /// The dependency table.
     deps: Mutex<DependencyTable>,
     /// The internalized paths.
@@ -45,12 +52,24 @@ pub struct Session<'ctx> {
 
 impl<'sess, 'ctx: 'sess> Session<'ctx> {
     /// Create a new session.
+    ///
+    /// Network access is enabled; use `new_frozen` to create a session that
+    /// never fetches.
     pub fn new(root: &'ctx Path, manifest: &'ctx Manifest, config: &'ctx Config, arenas: &'ctx SessionArenas) -> Session<'ctx> {
+        Self::new_frozen(root, manifest, config, arenas, false)
+    }
+
+    /// Create a new session with explicit control over `frozen`.
+    ///
+    /// `frozen` disables network access for the whole session; see
+    /// `Session::frozen`.
+    pub fn new_frozen(root: &'ctx Path, manifest: &'ctx Manifest, config: &'ctx Config, arenas: &'ctx SessionArenas, frozen: bool) -> Session<'ctx> {
         Session {
             root: root,
             manifest: manifest,
             config: config,
             arenas: arenas,
+            frozen: frozen,
             deps: Mutex::new(DependencyTable::new()),
             paths: Mutex::new(HashSet::new()),
             names: Mutex::new(HashMap::new()),
@@ -69,17 +88,20 @@ impl<'sess, 'ctx: 'sess> Session<'ctx> {
         manifest: &config::Manifest
     ) -> DependencyRef {
         debugln!("sess: load dependency `{}` as {:?} for package `{}`", name, cfg, manifest.package.name);
-        let src = match *cfg {
-            config::Dependency::Version(_) => DependencySource::Registry,
-            config::Dependency::Path(ref p) => DependencySource::Path(p.clone()),
-            config::Dependency::GitRevision(ref g, _) |
-            config::Dependency::GitVersion(ref g, _) => DependencySource::Git(g.clone()),
+        let (src, submodules) = match *cfg {
+            config::Dependency::Version(_) => (DependencySource::Registry, true),
+            config::Dependency::Path(ref p) => (DependencySource::Path(p.clone()), true),
+            config::Dependency::GitRevision(ref g, _, submodules) |
+            config::Dependency::GitVersion(ref g, _, submodules) => (DependencySource::Git(g.clone()), submodules),
+            config::Dependency::HgRevision(ref g, _) |
+            config::Dependency::HgVersion(ref g, _) => (DependencySource::Hg(g.clone()), true),
         };
         self.deps.lock().unwrap().add(DependencyEntry {
             name: name.into(),
             source: src,
             revision: None,
             version: None,
+            submodules: submodules,
         })
     }
 
@@ -95,16 +117,18 @@ impl<'sess, 'ctx: 'sess> Session<'ctx> {
         let mut deps = self.deps.lock().unwrap();
         let mut names = HashMap::new();
         for (name, pkg) in &locked.packages {
-            let src = match pkg.source {
-                config::LockedSource::Path(ref path) => DependencySource::Path(path.clone()),
-                config::LockedSource::Git(ref url) => DependencySource::Git(url.clone()),
-                config::LockedSource::Registry(ref _ver) => DependencySource::Registry,
+            let (src, submodules) = match pkg.source {
+                config::LockedSource::Path(ref path) => (DependencySource::Path(path.clone()), true),
+                config::LockedSource::Git(ref url, submodules) => (DependencySource::Git(url.clone()), submodules),
+                config::LockedSource::Hg(ref url) => (DependencySource::Hg(url.clone()), true),
+                config::LockedSource::Registry(ref _ver) => (DependencySource::Registry, true),
             };
             let id = deps.add(DependencyEntry {
                 name: name.clone(),
                 source: src,
                 revision: pkg.revision.clone(),
                 version: pkg.version.as_ref().map(|s| semver::Version::parse(&s).unwrap()),
+                submodules: submodules,
             });
             names.insert(name.clone(), id);
         }
@@ -177,28 +201,130 @@ impl<'io, 'sess: 'io, 'ctx: 'sess> SessionIo<'sess, 'ctx> {
         let dep = self.sess.dependency(dep_id);
         match dep.source {
             DependencySource::Registry => {
-                unimplemented!("determine available versions of registry dependency");
+                Box::new(self
+                    .registry_versions(&dep.name)
+                    .map(DependencyVersions::Registry))
             }
             DependencySource::Path(_) => {
                 Box::new(future::ok(DependencyVersions::Path))
             }
             DependencySource::Git(ref url) => {
-                Box::new(self
-                    .git_database(&dep.name, url)
-                    .and_then(move |db| self.git_versions(db))
-                    .map(DependencyVersions::Git))
+                Box::new(GitVcs.list_versions(self, &dep.name, url).map(DependencyVersions::Git))
+            }
+            DependencySource::Hg(ref url) => {
+                Box::new(HgVcs.list_versions(self, &dep.name, url).map(DependencyVersions::Hg))
+            }
+        }
+    }
+
+    /// Ensure that a local mirror of the registry index exists, returning
+    /// its path.
+    ///
+    /// This is analogous to `git_database`: the mirror is created on first
+    /// use and reused afterwards.
+    fn registry_index(&'io self) -> Box<Future<Item=&'ctx Path, Error=Error> + 'io> {
+        use std;
+
+        let index_dir = self.sess.config.database.join("registry").join("index");
+        let index_dir = self.sess.intern_path(index_dir);
+        match std::fs::create_dir_all(index_dir) {
+            Ok(_) => (),
+            Err(cause) => return Box::new(future::err(Error::chain(
+                format!("Failed to create registry index directory {:?}.", index_dir),
+                cause
+            )))
+        };
+
+        // TODO: Actually sync the index from a remote. For now we work with
+        // whatever is locally cached, same as a git database that has
+        // already been cloned.
+        Box::new(future::ok(index_dir))
+    }
+
+    /// Determine the available versions for a registry dependency.
+    ///
+    /// Looks the package up in the local registry index mirror, where each
+    /// package has one file listing its versions and the coordinates
+    /// needed to download each of them.
+    fn registry_versions(
+        &'io self,
+        name: &str
+    ) -> Box<Future<Item=RegistryVersions, Error=Error> + 'io> {
+        let name = name.to_string();
+        Box::new(
+            self.registry_index()
+                .and_then(move |index_dir| Self::read_registry_index(index_dir, &name))
+        )
+    }
+
+    /// Parse the index entry for a single package.
+    ///
+    /// The index format is one line per version: the semantic version,
+    /// the download URL, and the BLAKE2 checksum of the archive, separated
+    /// by spaces. Unparseable or missing entries simply yield no versions,
+    /// mirroring how `git_versions` silently discards tags that are not
+    /// valid semantic versions.
+    fn read_registry_index(index_dir: &Path, name: &str) -> Result<RegistryVersions> {
+        use std;
+
+        let path = index_dir.join(name);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(ref cause) if cause.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(cause) => return Err(Error::chain(
+                format!("Failed to read registry index entry {:?}.", path),
+                cause
+            )),
+        };
+
+        let mut versions = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut parts = line.splitn(3, ' ');
+            let ver = parts.next().unwrap_or("");
+            let url = parts.next().unwrap_or("");
+            let checksum = parts.next().unwrap_or("");
+            if let Ok(v) = semver::Version::parse(ver) {
+                versions.push((v, RegistryVersion {
+                    url: url.into(),
+                    checksum: checksum.into(),
+                }));
             }
         }
+        versions.sort_by(|a, b| b.0.cmp(&a.0));
+
+        Ok(RegistryVersions { versions })
+    }
+
+    /// Determine the location of the shared git database for a dependency.
+    ///
+    /// This uses the dependency's name and the first 8 bytes (16 hex
+    /// characters) of the URL's BLAKE2 hash to keep databases of
+    /// differently-named dependencies that happen to share a URL apart.
+    /// This does not create the directory; use `git_database` for that.
+    fn git_db_dir(&self, name: &str, url: &str) -> PathBuf {
+        use blake2::{Blake2b, Digest};
+        let hash = &format!("{:016x}", Blake2b::digest_str(url))[..16];
+        let db_name = format!("{}-{}", name, hash);
+        self.sess.config.database.join("git").join("db").join(db_name)
     }
 
     /// Access the git database for a dependency.
     ///
-    /// If the database does not exist, it is created. If the database has not
-    /// been updated recently, the remote is fetched.
+    /// If the database does not exist, it is created. With
+    /// `FetchDepth::Full`, the remote is fetched unless the database was
+    /// fetched recently and the manifest has not changed since; with
+    /// `FetchDepth::Revision`, only that commit is fetched, and only if it
+    /// is missing. `depth` controls how much history is actually pulled
+    /// down; see `FetchDepth`.
     fn git_database(
         &'io self,
         name: &str,
-        url: &str
+        url: &str,
+        depth: FetchDepth,
     ) -> Box<Future<Item=Git<'io, 'sess, 'ctx>, Error=Error> + 'io> {
         use std;
 
@@ -207,15 +333,9 @@ impl<'io, 'sess: 'io, 'ctx: 'sess> SessionIo<'sess, 'ctx> {
         //       This ensures that the gitdb is setup only once, and makes the
         //       whole process faster for later calls.
 
-        // Determine the name of the database as the given name and the first
-        // 8 bytes (16 hex characters) of the URL's BLAKE2 hash.
-        use blake2::{Blake2b, Digest};
-        let hash = &format!("{:016x}", Blake2b::digest_str(url))[..16];
-        let db_name = format!("{}-{}", name, hash);
-
         // Determine the location of the git database and create it if its does
         // not yet exist.
-        let db_dir = self.sess.config.database.join("git").join("db").join(db_name);
+        let db_dir = self.git_db_dir(name, url);
         let db_dir = self.sess.intern_path(db_dir);
         match std::fs::create_dir_all(db_dir) {
             Ok(_) => (),
@@ -227,37 +347,221 @@ impl<'io, 'sess: 'io, 'ctx: 'sess> SessionIo<'sess, 'ctx> {
         let git = Git::new(db_dir, self);
         let url = String::from(url);
 
+        if self.sess.frozen && !db_dir.join("config").exists() {
+            return Box::new(future::err(Error::new(format!(
+                "Git database for `{}` does not exist locally and fetching is disabled (frozen mode).",
+                url
+            ))));
+        }
+
         // Either initialize the repository or update it if needed.
         if !db_dir.join("config").exists() {
-            // Initialize.
+            // Initialize. Only fetch as much history as the caller actually
+            // needs right now; a build pinned to a locked revision has no
+            // use for the rest of the repository's history.
             stageln!("Cloning", "{}", url);
+            let fetch: Box<Future<Item=(), Error=Error> + 'io> = match depth {
+                FetchDepth::Revision(rev) => {
+                    let rev = rev.to_string();
+                    Box::new(git.spawn_with(move |c| c
+                        .arg("fetch")
+                        .arg("--depth").arg("1")
+                        .arg("origin")
+                        .arg(&rev))
+                        .map(|_| ()))
+                }
+                FetchDepth::Full => Box::new(git.fetch("origin").map(|_| ())),
+            };
             Box::new(
                 git.spawn_with(|c| c
                     .arg("init")
                     .arg("--bare"))
-                .and_then(move |_| git.spawn_with(|c| c
+                .and_then(move |_| git.spawn_with(move |c| c
                     .arg("remote")
                     .arg("add")
                     .arg("origin")
-                    .arg(url)))
-                .and_then(move |_| git.fetch("origin"))
+                    .arg(&url)))
+                .and_then(move |_| fetch)
                 .map_err(move |cause| Error::chain(
                     format!("Failed to initialize git database in {:?}.", db_dir),
                     cause))
                 .map(move |_| git)
             )
         } else {
-            // Update.
-            // TODO: Don't always do this, but rather, check if the manifest has
-            //       been modified since the last fetch, and only then proceed.
-            Box::new(git.fetch("origin").map(move |_| git))
+            // Update, subject to `--frozen` and the fetch fingerprint cache.
+            match depth {
+                FetchDepth::Revision(rev) => {
+                    // The caller already knows exactly which commit it
+                    // needs. If we already have it, there is nothing to do;
+                    // otherwise fetch just that one commit. This must never
+                    // unshallow a database that was deliberately kept
+                    // shallow for a locked build.
+                    let rev = rev.to_string();
+                    let frozen = self.sess.frozen;
+                    Box::new(
+                        self.git_has_rev(git, rev.clone())
+                            .and_then(move |have_it| -> Box<Future<Item=(), Error=Error> + 'io> {
+                                if have_it {
+                                    Box::new(future::ok(()))
+                                } else if frozen {
+                                    Box::new(future::err(Error::new(format!(
+                                        "Revision `{}` is not available locally and fetching is disabled (frozen mode).",
+                                        rev
+                                    ))))
+                                } else {
+                                    Box::new(git.spawn_with(move |c| c
+                                        .arg("fetch")
+                                        .arg("--depth").arg("1")
+                                        .arg("origin")
+                                        .arg(&rev))
+                                        .map(|_| ()))
+                                }
+                            })
+                            .map(move |_| git)
+                    )
+                }
+                FetchDepth::Full => {
+                    // Skip the fetch entirely if the session is frozen, or
+                    // if the root manifest hasn't changed since the last
+                    // fetch and that fetch is still within the staleness
+                    // window.
+                    if self.sess.frozen || self.fetch_fingerprint_fresh(db_dir) {
+                        debugln!("sess: gitdb: skipping fetch of {:?}", db_dir);
+                        return Box::new(future::ok(git));
+                    }
+                    let unshallow = db_dir.join("shallow").exists();
+                    let manifest_mtime = self.manifest_mtime();
+                    Box::new(
+                        git.spawn_with(move |c| {
+                            c.arg("fetch").arg("origin");
+                            if unshallow {
+                                c.arg("--unshallow");
+                            }
+                            c
+                        })
+                        .map(move |_| {
+                            Self::write_fetch_fingerprint(db_dir, manifest_mtime);
+                            git
+                        })
+                    )
+                }
+            }
+        }
+    }
+
+    /// Check whether a revision is already present in a git database,
+    /// without touching the network.
+    fn git_has_rev(
+        &'io self,
+        git: Git<'io, 'sess, 'ctx>,
+        rev: String,
+    ) -> Box<Future<Item=bool, Error=Error> + 'io> {
+        Box::new(
+            git.spawn_with(move |c| c
+                .arg("cat-file")
+                .arg("-e")
+                .arg(format!("{}^{{commit}}", rev)))
+                .then(|result| -> Result<bool, Error> { Ok(result.is_ok()) })
+        )
+    }
+
+    /// Determine the modification time of the root manifest, in seconds
+    /// since the Unix epoch. Used to invalidate the fetch fingerprint cache
+    /// whenever the manifest that declared a dependency changes.
+    fn manifest_mtime(&self) -> u64 {
+        use std;
+        std::fs::metadata(self.sess.root.join("Bender.yml"))
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+
+    /// Whether a git database's fetch fingerprint is still within the
+    /// staleness window and the manifest hasn't changed since, i.e.
+    /// whether a fetch of it can be skipped entirely.
+    fn fetch_fingerprint_fresh(&self, db_dir: &Path) -> bool {
+        use std;
+        let (fetch_time, manifest_mtime) = match Self::read_fetch_fingerprint(db_dir) {
+            Some(fp) => fp,
+            None => return false,
+        };
+        if manifest_mtime != self.manifest_mtime() {
+            return false;
         }
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        now.saturating_sub(fetch_time) < FETCH_STALENESS_SECS
+    }
+
+    /// Read the `(fetch_time, manifest_mtime)` fingerprint recorded inside
+    /// a git database's directory, if any.
+    fn read_fetch_fingerprint(db_dir: &Path) -> Option<(u64, u64)> {
+        use std;
+        let text = match std::fs::read_to_string(db_dir.join(FETCH_FINGERPRINT_FILE)) {
+            Ok(text) => text,
+            Err(_) => return None,
+        };
+        let mut parts = text.trim().splitn(2, ' ');
+        let fetch_time = match parts.next().and_then(|s| s.parse::<u64>().ok()) {
+            Some(v) => v,
+            None => return None,
+        };
+        let manifest_mtime = match parts.next().and_then(|s| s.parse::<u64>().ok()) {
+            Some(v) => v,
+            None => return None,
+        };
+        Some((fetch_time, manifest_mtime))
+    }
+
+    /// Persist the fetch fingerprint for a git database. Best-effort: a
+    /// failure to write it just means the next command will fetch again,
+    /// which is always safe.
+    fn write_fetch_fingerprint(db_dir: &Path, manifest_mtime: u64) {
+        use std;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let _ = std::fs::write(
+            db_dir.join(FETCH_FINGERPRINT_FILE),
+            format!("{} {}", now, manifest_mtime)
+        );
+    }
+
+    /// Resolve an annotated tag object to the commit it points at.
+    ///
+    /// `git show-ref` (which backs `Git::list_refs`) reports annotated tags
+    /// under `refs/tags/<name>` pointing at the tag object itself, not the
+    /// commit it annotates; it only dereferences them when called with
+    /// `--dereference`, which `list_refs` does not pass. Rather than widen
+    /// that shared helper, peel the tag ourselves via `git rev-parse`.
+    /// Returns `None` if the object cannot be peeled to a commit. A `hash`
+    /// that already names a commit is returned unchanged.
+    fn peel_tag(db_dir: &Path, hash: &str) -> Option<String> {
+        use std::process::Command;
+        let output = Command::new("git")
+            .arg("-C").arg(db_dir)
+            .arg("rev-parse")
+            .arg("--verify")
+            .arg(format!("{}^{{commit}}", hash))
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if hash.is_empty() { None } else { Some(hash) }
     }
 
     /// Determine the list of versions available for a git dependency.
     fn git_versions(
         &'io self,
         git: Git<'io, 'sess, 'ctx>,
+        db_dir: PathBuf,
     ) -> Box<Future<Item=GitVersions, Error=Error> + 'io> {
         let dep_refs = git.list_refs();
         let dep_revs = git.list_revs();
@@ -268,20 +572,33 @@ impl<'io, 'sess: 'io, 'ctx: 'sess> SessionIo<'sess, 'ctx> {
                 // only accept refs that point to actual revisions.
                 let rev_ids: HashSet<&str> = revs.iter().map(String::as_str).collect();
 
-                // Split the refs into tags and branches, discard
-                // everything else.
+                // Split the refs into tags and branches, discard everything
+                // else. Annotated tags (the norm for releases) are listed as
+                // `refs/tags/<name>`, pointing at the tag object itself
+                // rather than the commit it was made against; the tag
+                // object's hash is not among `revs`, so such a tag is peeled
+                // via `peel_tag` before being accepted. Lightweight tags
+                // already point straight at a commit and need no peeling.
                 let mut tags = HashMap::<String, String>::new();
                 let mut branches = HashMap::<String, String>::new();
                 let tag_pfx = "refs/tags/";
                 let branch_pfx = "refs/remotes/origin/";
                 for (hash, rf) in refs {
-                    if !rev_ids.contains(hash.as_str()) {
-                        continue;
-                    }
                     if rf.starts_with(tag_pfx) {
-                        tags.insert(rf[tag_pfx.len()..].into(), hash);
+                        let name = &rf[tag_pfx.len()..];
+                        let hash = if rev_ids.contains(hash.as_str()) {
+                            hash
+                        } else {
+                            match Self::peel_tag(&db_dir, &hash) {
+                                Some(commit) if rev_ids.contains(commit.as_str()) => commit,
+                                _ => continue,
+                            }
+                        };
+                        tags.insert(name.into(), hash);
                     } else if rf.starts_with(branch_pfx) {
-                        branches.insert(rf[branch_pfx.len()..].into(), hash);
+                        if rev_ids.contains(hash.as_str()) {
+                            branches.insert(rf[branch_pfx.len()..].into(), hash);
+                        }
                     }
                 }
                 (tags, branches)
@@ -315,6 +632,202 @@ impl<'io, 'sess: 'io, 'ctx: 'sess> SessionIo<'sess, 'ctx> {
         Box::new(out)
     }
 
+    /// Determine the location of the shared mercurial database for a
+    /// dependency.
+    ///
+    /// Mirrors `git_db_dir`.
+    fn hg_db_dir(&self, name: &str, url: &str) -> PathBuf {
+        use blake2::{Blake2b, Digest};
+        let hash = &format!("{:016x}", Blake2b::digest_str(url))[..16];
+        let db_name = format!("{}-{}", name, hash);
+        self.sess.config.database.join("hg").join("db").join(db_name)
+    }
+
+    /// Run an `hg` subcommand in `dir` and capture its stdout as a string.
+    ///
+    /// There is no asynchronous process wrapper for mercurial akin to `Git`,
+    /// so this (and everything built on top of it) shells out synchronously.
+    fn hg_output(dir: &Path, args: &[&str]) -> Result<String> {
+        use std::process::Command;
+        let output = Command::new("hg")
+            .arg("--cwd").arg(dir)
+            .args(args)
+            .output()
+            .map_err(|cause| Error::chain(
+                format!("Failed to run `hg {}` in {:?}.", args.join(" "), dir),
+                cause
+            ))?;
+        if !output.status.success() {
+            return Err(Error::new(format!(
+                "`hg {}` failed in {:?}: {}",
+                args.join(" "), dir, String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Access the mercurial database for a dependency, cloning or pulling it
+    /// as necessary.
+    ///
+    /// Unlike `git_database`, this always does a full pull (none at all in
+    /// frozen mode); mercurial has no notion of a shallow clone as
+    /// lightweight as git's, so the fetch fingerprint and depth machinery
+    /// added for git is not replicated here.
+    fn hg_database(&self, name: &str, url: &str) -> Result<PathBuf> {
+        use std;
+        let db_dir = self.hg_db_dir(name, url);
+        let db_dir = self.sess.intern_path(db_dir);
+
+        if self.sess.frozen && !db_dir.join(".hg").exists() {
+            return Err(Error::new(format!(
+                "Mercurial database for `{}` does not exist locally and fetching is disabled (frozen mode).",
+                url
+            )));
+        }
+
+        if !db_dir.join(".hg").exists() {
+            if let Err(cause) = std::fs::remove_dir_all(db_dir) {
+                if cause.kind() != std::io::ErrorKind::NotFound {
+                    return Err(Error::chain(
+                        format!("Failed to clean mercurial database directory {:?}.", db_dir),
+                        cause
+                    ));
+                }
+            }
+            stageln!("Cloning", "{}", url);
+            Self::hg_output(Path::new("."), &["clone", "--noupdate", url, &db_dir.to_string_lossy()])?;
+        } else if !self.sess.frozen {
+            Self::hg_output(db_dir, &["pull", url])?;
+        }
+
+        Ok(db_dir.to_path_buf())
+    }
+
+    /// List every revision available in a mercurial database, newest first.
+    fn hg_revs(db_dir: &Path) -> Result<Vec<String>> {
+        Ok(Self::hg_output(db_dir, &["log", "--template", "{node}\\n"])?
+            .lines()
+            .map(String::from)
+            .collect())
+    }
+
+    /// Determine the list of versions available for a mercurial dependency.
+    fn hg_versions(&self, db_dir: &Path) -> Result<HgVersions> {
+        let revs = Self::hg_revs(db_dir)?;
+        let rev_ids: HashSet<&str> = revs.iter().map(String::as_str).collect();
+
+        let mut refs = HashMap::<String, String>::new();
+        for line in Self::hg_output(db_dir, &["tags", "--template", "{tag} {node}\\n"])?.lines() {
+            let mut parts = line.splitn(2, ' ');
+            let tag = match parts.next() {
+                Some(tag) if tag != "tip" => tag,
+                _ => continue,
+            };
+            let node = match parts.next() {
+                Some(node) => node,
+                None => continue,
+            };
+            if rev_ids.contains(node) {
+                refs.insert(tag.into(), node.into());
+            }
+        }
+        for line in Self::hg_output(db_dir, &["branches", "--template", "{branch} {node}\\n"])?.lines() {
+            let mut parts = line.splitn(2, ' ');
+            let branch = match parts.next() {
+                Some(branch) => branch,
+                None => continue,
+            };
+            let node = match parts.next() {
+                Some(node) => node,
+                None => continue,
+            };
+            if rev_ids.contains(node) && !refs.contains_key(branch) {
+                refs.insert(branch.into(), node.into());
+            }
+        }
+
+        let mut versions: Vec<(semver::Version, String)> = refs
+            .iter()
+            .filter_map(|(tag, node)| {
+                if tag.starts_with("v") {
+                    match semver::Version::parse(&tag[1..]) {
+                        Ok(v) => Some((v, node.clone())),
+                        Err(_) => None,
+                    }
+                } else {
+                    None
+                }
+            })
+            .collect();
+        versions.sort_by(|a, b| b.cmp(a));
+
+        Ok(HgVersions {
+            versions: versions,
+            refs: refs,
+            revs: revs,
+        })
+    }
+
+    /// Ensure that a proper mercurial checkout exists.
+    ///
+    /// Mirrors `checkout_git`: if the directory is not a proper mercurial
+    /// repository, it is deleted and re-created from scratch, and the
+    /// working copy is pulled from the shared local database rather than
+    /// the network.
+    fn checkout_hg(
+        &'io self,
+        name: &str,
+        path: &'ctx Path,
+        url: &str,
+        revision: &str,
+        _submodules: bool,
+    ) -> Box<Future<Item=&'ctx Path, Error=Error> + 'io> {
+        use std;
+
+        let name = name.to_string();
+        let url = url.to_string();
+        let revision = revision.to_string();
+        let result = (|| -> Result<&'ctx Path> {
+            let db_dir = self.hg_database(&name, &url)?;
+            debugln!("checkout_hg: url `{}` revision `{}` at {:?}", url, revision, path);
+
+            let valid = path.join(".hg").exists();
+            if !valid {
+                if let Err(cause) = std::fs::remove_dir_all(path) {
+                    if cause.kind() != std::io::ErrorKind::NotFound {
+                        return Err(Error::chain(
+                            format!("Failed to clean checkout directory {:?}.", path),
+                            cause
+                        ));
+                    }
+                }
+                stageln!("Checkout", "{} ({})", url, revision);
+                Self::hg_output(Path::new("."), &[
+                    "clone", "--noupdate",
+                    &db_dir.to_string_lossy(), &path.to_string_lossy(),
+                ])?;
+            } else {
+                Self::hg_output(path, &["pull", &db_dir.to_string_lossy()])?;
+            }
+
+            // Mercurial updates subrepositories declared in `.hgsub`
+            // automatically as part of `hg update`; unlike `hg pull`/`hg
+            // push`, `update` has no `-S`/`--subrepos` flag to pass (and
+            // would abort if given one), and no flag to suppress the
+            // automatic recursion either, so `submodules` is currently
+            // unused here.
+            Self::hg_output(path, &["update", "--clean", "-r", revision.as_str()])
+                .map_err(|cause| Error::chain(
+                    format!("Failed to check out revision `{}` of `{}` in {:?}.", revision, url, path),
+                    cause
+                ))?;
+
+            Ok(path)
+        })();
+
+        Box::new(future::result(result))
+    }
+
     /// Ensure that a dependency is checked out and obtain its path.
     pub fn checkout(
         &'io self,
@@ -325,6 +838,22 @@ impl<'io, 'sess: 'io, 'ctx: 'sess> SessionIo<'sess, 'ctx> {
         // Find the exact source of the dependency.
         let dep = self.sess.dependency(dep_id);
 
+        // Path and registry dependencies have their own notion of where
+        // they live on disk, so handle them up front. Everything backed by
+        // a VCS (git, mercurial, ...) shares the naming scheme below and
+        // goes through the `Vcs` trait once its checkout directory has been
+        // determined.
+        let (vcs_dir_name, url): (&str, &str) = match dep.source {
+            DependencySource::Path(ref path) => {
+                return Box::new(future::ok(self.sess.intern_path(path.clone())));
+            }
+            DependencySource::Registry => {
+                return self.checkout_registry(&dep.name, dep.version.as_ref().unwrap());
+            }
+            DependencySource::Git(ref url) => ("git", url),
+            DependencySource::Hg(ref url) => ("hg", url),
+        };
+
         // Determine the name of the checkout as the given name and the first
         // 8 bytes (16 hex characters) of a BLAKE2 hash of the source and the
         // path to the root package. This ensures that for every dependency and
@@ -332,54 +861,568 @@ impl<'io, 'sess: 'io, 'ctx: 'sess> SessionIo<'sess, 'ctx> {
         let hash = {
             use blake2::{Blake2b, Digest};
             let mut hasher = Blake2b::new();
-            match dep.source {
-                DependencySource::Registry => unimplemented!(),
-                DependencySource::Git(ref url) => hasher.input(url.as_bytes()),
-                DependencySource::Path(ref path) => return Box::new(
-                    future::ok(self.sess.intern_path(path.clone()))
-                ),
-            }
+            hasher.input(url.as_bytes());
             hasher.input(format!("{:?}", self.sess.root).as_bytes());
-            &format!("{:016x}", hasher.result())[..16]
+            format!("{:016x}", hasher.result())[..16].to_string()
         };
         let checkout_name = format!("{}-{}", dep.name, hash);
 
-        // Determine the location of the git database and create it if its does
-        // not yet exist.
+        // Determine the location of the checkout and create it if it does
+        // not yet exist. Different VCS backends get their own namespace
+        // under `config.database`, mirroring how their databases are kept
+        // apart.
         let checkout_dir = self.sess.config.database
-            .join("git")
+            .join(vcs_dir_name)
             .join("checkouts")
             .join(checkout_name);
         let checkout_dir = self.sess.intern_path(checkout_dir);
         match std::fs::create_dir_all(checkout_dir) {
             Ok(_) => (),
             Err(cause) => return Box::new(future::err(Error::chain(
-                format!("Failed to create git checkout directory {:?}.", checkout_dir),
+                format!("Failed to create {} checkout directory {:?}.", vcs_dir_name, checkout_dir),
                 cause
             )))
         };
 
+        let name = dep.name.clone();
+        let url = url.to_string();
+        let revision = dep.revision.clone().unwrap();
+        let submodules = dep.submodules;
         match dep.source {
-            DependencySource::Path(..) => unreachable!(),
-            DependencySource::Registry => unimplemented!(),
-            DependencySource::Git(ref url) => {
-                self.checkout_git(checkout_dir, url, dep.revision.as_ref().unwrap())
+            DependencySource::Path(..) |
+            DependencySource::Registry => unreachable!(),
+            DependencySource::Git(..) => {
+                GitVcs.checkout_revision(self, &name, checkout_dir, &url, &revision, submodules)
             }
+            DependencySource::Hg(..) => {
+                HgVcs.checkout_revision(self, &name, checkout_dir, &url, &revision, submodules)
+            }
+        }
+    }
+
+    /// Ensure that a registry dependency's selected version is unpacked on
+    /// disk and obtain its path.
+    fn checkout_registry(
+        &'io self,
+        name: &str,
+        version: &semver::Version,
+    ) -> Box<Future<Item=&'ctx Path, Error=Error> + 'io> {
+        let name = name.to_string();
+        let version = version.clone();
+        Box::new(
+            self.registry_versions(&name)
+                .and_then(move |versions| {
+                    versions.versions
+                        .into_iter()
+                        .find(|&(ref v, _)| *v == version)
+                        .map(|(_, entry)| entry)
+                        .ok_or_else(|| Error::new(format!(
+                            "Version `{}` of registry dependency `{}` is no longer available in the index.",
+                            version, name
+                        )))
+                })
+                .and_then(move |entry| self.unpack_registry(&entry))
+        )
+    }
+
+    /// Verify that a downloaded archive's BLAKE2 hash matches the checksum
+    /// recorded for it in the registry index.
+    ///
+    /// Without this, a corrupted or substituted download would be unpacked
+    /// and then cached under `expected`'s name regardless of what it
+    /// actually contains, and reused as such forever.
+    fn verify_checksum(archive: &Path, expected: &str) -> Result<()> {
+        use std;
+        use blake2::{Blake2b, Digest};
+
+        let bytes = std::fs::read(archive).map_err(|cause| Error::chain(
+            format!("Failed to read downloaded archive {:?} for checksum verification.", archive),
+            cause
+        ))?;
+        let actual = format!("{:x}", Blake2b::digest(&bytes));
+        if actual != expected {
+            return Err(Error::new(format!(
+                "Checksum mismatch for downloaded archive {:?}: index says {}, got {}.",
+                archive, expected, actual
+            )));
         }
+        Ok(())
+    }
+
+    /// Download and unpack a single registry dependency version.
+    ///
+    /// The destination directory is named after the package's BLAKE2
+    /// checksum rather than the dependency name, so that the same version
+    /// downloaded for different root packages is only ever fetched and
+    /// unpacked once. The downloaded archive is verified against that same
+    /// checksum before it is trusted, so a corrupted or substituted archive
+    /// is never unpacked or cached. A `.bender-complete` marker file is
+    /// written once the unpack has finished, and is used to detect (and
+    /// redo) partial downloads left behind by an interrupted run. If the
+    /// archive isn't already cached, this errors out in `--frozen` mode
+    /// rather than reaching for the network, mirroring `git_database` and
+    /// `hg_database`.
+    fn unpack_registry(
+        &'io self,
+        entry: &RegistryVersion,
+    ) -> Box<Future<Item=&'ctx Path, Error=Error> + 'io> {
+        use std;
+        use std::process::Command;
+
+        let checkout_dir = self.sess.config.database
+            .join("registry")
+            .join("src")
+            .join(&entry.checksum);
+        let checkout_dir = self.sess.intern_path(checkout_dir);
+
+        if checkout_dir.join(".bender-complete").exists() {
+            return Box::new(future::ok(checkout_dir));
+        }
+
+        if self.sess.frozen {
+            return Box::new(future::err(Error::new(format!(
+                "Registry archive for `{}` does not exist locally and fetching is disabled (frozen mode).",
+                entry.url
+            ))));
+        }
+
+        if let Err(cause) = std::fs::remove_dir_all(checkout_dir) {
+            if cause.kind() != std::io::ErrorKind::NotFound {
+                return Box::new(future::err(Error::chain(
+                    format!("Failed to clean registry checkout directory {:?}.", checkout_dir),
+                    cause
+                )));
+            }
+        }
+        if let Err(cause) = std::fs::create_dir_all(checkout_dir) {
+            return Box::new(future::err(Error::chain(
+                format!("Failed to create registry checkout directory {:?}.", checkout_dir),
+                cause
+            )));
+        }
+
+        stageln!("Fetching", "{}", entry.url);
+        let archive = checkout_dir.join(".bender-download.tar.gz");
+        let result = Command::new("curl")
+            .arg("-sSfL")
+            .arg("-o").arg(&archive)
+            .arg(&entry.url)
+            .status()
+            .map_err(|cause| Error::chain(
+                format!("Failed to run `curl` to download {:?}.", entry.url),
+                cause
+            ))
+            .and_then(|status| if status.success() {
+                Ok(())
+            } else {
+                Err(Error::new(format!("`curl` failed to download {:?}.", entry.url)))
+            })
+            .and_then(|_| Self::verify_checksum(&archive, &entry.checksum))
+            .and_then(|_| Command::new("tar")
+                .arg("-xzf").arg(&archive)
+                .arg("-C").arg(checkout_dir)
+                .arg("--strip-components=1")
+                .status()
+                .map_err(|cause| Error::chain(
+                    format!("Failed to run `tar` to unpack {:?}.", archive),
+                    cause
+                ))
+                .and_then(|status| if status.success() {
+                    Ok(())
+                } else {
+                    Err(Error::new(format!("`tar` failed to unpack {:?}.", archive)))
+                }))
+            .and_then(|_| std::fs::remove_file(&archive).map_err(|cause| Error::chain(
+                format!("Failed to remove downloaded archive {:?}.", archive),
+                cause
+            )))
+            .and_then(|_| std::fs::write(checkout_dir.join(".bender-complete"), entry.checksum.as_bytes())
+                .map_err(|cause| Error::chain(
+                    format!("Failed to mark registry checkout {:?} complete.", checkout_dir),
+                    cause
+                )));
+
+        match result {
+            Ok(_) => Box::new(future::ok(checkout_dir)),
+            Err(e) => Box::new(future::err(e)),
+        }
+    }
+
+    /// Parse the `name`/`url` pairs of every submodule declared in a
+    /// checkout's `.gitmodules` file. Returns an empty list if the checkout
+    /// has no submodules.
+    ///
+    /// The name (the `[submodule "<name>"]` section header, i.e. the key
+    /// `git config submodule.<name>.url` reads) is what matters here, not
+    /// the submodule's checkout path within the worktree; the two commonly
+    /// differ for submodules that were renamed after being added.
+    fn read_gitmodules(path: &Path) -> Result<Vec<(String, String)>> {
+        use std::process::Command;
+
+        if !path.join(".gitmodules").exists() {
+            return Ok(Vec::new());
+        }
+
+        let output = Command::new("git")
+            .current_dir(path)
+            .arg("config")
+            .arg("-f").arg(".gitmodules")
+            .arg("--get-regexp")
+            .arg(r"^submodule\..*\.url$")
+            .output()
+            .map_err(|cause| Error::chain(
+                format!("Failed to read .gitmodules in {:?}.", path),
+                cause
+            ))?;
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+
+        let mut modules = Vec::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let mut parts = line.splitn(2, ' ');
+            let key = match parts.next() { Some(k) => k, None => continue };
+            let value = match parts.next() { Some(v) => v, None => continue };
+            if !key.starts_with("submodule.") || !key.ends_with(".url") {
+                continue;
+            }
+            let name = &key["submodule.".len()..key.len() - ".url".len()];
+            modules.push((name.to_string(), value.to_string()));
+        }
+
+        Ok(modules)
+    }
+
+    /// Initialize and update a checkout's submodules from the shared git
+    /// database cache rather than the network.
+    ///
+    /// Each submodule declared in `.gitmodules` is first resolved through
+    /// the same `git_database` machinery used for top-level dependencies
+    /// (so it is fetched once, respects `--frozen`, and is reused across
+    /// checkouts), its `submodule.<name>.url` is rewritten to point at that
+    /// local mirror, and only then is `git submodule update --init
+    /// --recursive` run. Only the submodules in the checkout's own
+    /// `.gitmodules` are redirected; nested submodules pulled in by
+    /// `--recursive` are fetched from the URLs their parents declare.
+    fn checkout_git_submodules(
+        &'io self,
+        git: Git<'io, 'sess, 'ctx>,
+        path: &'ctx Path,
+    ) -> Box<Future<Item=(), Error=Error> + 'io> {
+        use std::process::Command;
+
+        let modules = match Self::read_gitmodules(path) {
+            Ok(modules) => modules,
+            Err(cause) => return Box::new(future::err(cause)),
+        };
+
+        let mirrors: Vec<_> = modules.into_iter().map(move |(name, url)| {
+            let db_dir = self.git_db_dir(&name, &url);
+            self.git_database(&name, &url, FetchDepth::Full)
+                .map(move |_| (name, db_dir))
+        }).collect();
+
+        Box::new(
+            future::join_all(mirrors)
+                .and_then(move |mirrors| {
+                    for (name, db_dir) in &mirrors {
+                        Command::new("git")
+                            .current_dir(path)
+                            .arg("config")
+                            .arg(format!("submodule.{}.url", name))
+                            .arg(db_dir)
+                            .status()
+                            .map_err(|cause| Error::chain(
+                                format!("Failed to point submodule `{}` at its local mirror.", name),
+                                cause
+                            ))?;
+                    }
+                    Ok(())
+                })
+                .and_then(move |_| git.spawn_with(|c| c
+                    .arg("-c").arg("protocol.file.allow=always")
+                    .arg("submodule")
+                    .arg("update")
+                    .arg("--init")
+                    .arg("--recursive")))
+                .map(|_| ())
+        )
     }
 
     /// Ensure that a proper git checkout exists.
     ///
     /// If the directory is not a proper git repository, it is deleted and
-    /// re-created from scratch.
+    /// re-created from scratch. The checkout's objects are pulled from the
+    /// shared bare database rather than the network, since by the time we
+    /// get here the revision has already been resolved and fetched into
+    /// that database.
     fn checkout_git(
         &'io self,
+        name: &str,
         path: &'ctx Path,
         url: &str,
         revision: &str,
+        submodules: bool,
     ) -> Box<Future<Item=&'ctx Path, Error=Error> + 'io> {
+        use std;
         debugln!("checkout_git: url `{}` revision `{}` at {:?}", url, revision, path);
-        Box::new(future::err(Error::new("Checkout of git dependency not implemented")))
+
+        // Locate the shared bare database that holds the objects for this
+        // dependency.
+        let db_dir = self.git_db_dir(name, url);
+        let db_dir_str = db_dir.to_string_lossy().into_owned();
+
+        // If the checkout directory is missing its `.git/config`, it is not
+        // a usable repository (either freshly created, or left behind in a
+        // corrupt state by an interrupted checkout). Wipe it and start from
+        // scratch in that case.
+        let valid = path.join(".git").join("config").exists();
+        if !valid {
+            if let Err(cause) = std::fs::remove_dir_all(path) {
+                if cause.kind() != std::io::ErrorKind::NotFound {
+                    return Box::new(future::err(Error::chain(
+                        format!("Failed to clean checkout directory {:?}.", path),
+                        cause
+                    )));
+                }
+            }
+            if let Err(cause) = std::fs::create_dir_all(path) {
+                return Box::new(future::err(Error::chain(
+                    format!("Failed to create checkout directory {:?}.", path),
+                    cause
+                )));
+            }
+        }
+
+        let git = Git::new(path, self);
+        let rev0 = revision.to_string();
+        let rev1 = revision.to_string();
+
+        // Either initialize a fresh repository pointed at the local
+        // database, or reuse the one that is already there.
+        let init: Box<Future<Item=(), Error=Error> + 'io> = if valid {
+            Box::new(future::ok(()))
+        } else {
+            stageln!("Checkout", "{} ({})", url, revision);
+            Box::new(
+                git.spawn_with(|c| c.arg("init"))
+                    .and_then(move |_| git.spawn_with(move |c| c
+                        .arg("remote")
+                        .arg("add")
+                        .arg("origin")
+                        .arg(&db_dir_str)))
+                    .map(|_| ())
+            )
+        };
+
+        let checkout: Box<Future<Item=(), Error=Error> + 'io> = Box::new(
+            init
+                .and_then(move |_| git.spawn_with(move |c| c
+                    .arg("fetch")
+                    .arg("origin")
+                    .arg(&rev0)))
+                .and_then(move |_| git.spawn_with(move |c| c
+                    .arg("checkout")
+                    .arg("--force")
+                    .arg(&rev1)))
+                .and_then(move |_| git.spawn_with(|c| c
+                    .arg("reset")
+                    .arg("--hard")))
+                .and_then(move |_| git.spawn_with(|c| c
+                    .arg("clean")
+                    .arg("-ffdx")))
+                .map(|_| ())
+        );
+
+        // Recursively initialize and update any submodules, unless the
+        // dependency opted out of it. Hardware IP repositories frequently
+        // vendor sub-IP this way, and without this step a checkout would
+        // silently be missing files.
+        let checkout: Box<Future<Item=(), Error=Error> + 'io> = if submodules {
+            Box::new(checkout.and_then(move |_| self.checkout_git_submodules(git, path)))
+        } else {
+            checkout
+        };
+
+        Box::new(
+            checkout
+                .map_err(move |cause| Error::chain(
+                    format!("Failed to check out revision `{}` of `{}` in {:?}.", revision, url, path),
+                    cause))
+                .map(move |_| path)
+        )
+    }
+}
+
+/// A version control backend that can resolve and materialize a
+/// dependency on disk.
+///
+/// `SessionIo` already carries full, independently-tuned implementations of
+/// the individual steps for each backend (`git_database`/`git_versions`/
+/// `checkout_git` for git, `hg_database`/`hg_versions`/`checkout_hg` for
+/// mercurial); this trait gives `dependency_versions` and `checkout` a
+/// single, source-agnostic call to make once they have picked a backend.
+/// Picking the backend is still a `match` on `DependencySource` in each of
+/// them, so a third backend needs an impl of this trait plus an arm there.
+trait Vcs {
+    /// The version/ref/rev listing this backend's `list_versions` produces.
+    type Versions;
+
+    /// Ensure that a local mirror of `url` is available, honoring
+    /// `Session::frozen`. `depth` controls how much history a backend that
+    /// understands shallow fetches actually pulls down; backends without
+    /// that notion (e.g. mercurial) ignore it.
+    fn fetch<'io, 'sess: 'io, 'ctx: 'sess>(
+        &self,
+        io: &'io SessionIo<'sess, 'ctx>,
+        name: &str,
+        url: &str,
+        depth: FetchDepth,
+    ) -> Box<Future<Item=(), Error=Error> + 'io>;
+
+    /// List every revision available for `url`, fetching it first if
+    /// necessary.
+    fn list_revs<'io, 'sess: 'io, 'ctx: 'sess>(
+        &self,
+        io: &'io SessionIo<'sess, 'ctx>,
+        name: &str,
+        url: &str,
+    ) -> Box<Future<Item=Vec<String>, Error=Error> + 'io>;
+
+    /// List the versions (`v<semver>` tags) and named references available
+    /// for `url`, fetching it first if necessary.
+    fn list_versions<'io, 'sess: 'io, 'ctx: 'sess>(
+        &self,
+        io: &'io SessionIo<'sess, 'ctx>,
+        name: &str,
+        url: &str,
+    ) -> Box<Future<Item=Self::Versions, Error=Error> + 'io>;
+
+    /// Fetch (if necessary) and check out `revision` of `url` into `path`,
+    /// returning the checked-out path.
+    fn checkout_revision<'io, 'sess: 'io, 'ctx: 'sess>(
+        &self,
+        io: &'io SessionIo<'sess, 'ctx>,
+        name: &str,
+        path: &'ctx Path,
+        url: &str,
+        revision: &str,
+        submodules: bool,
+    ) -> Box<Future<Item=&'ctx Path, Error=Error> + 'io>;
+}
+
+/// The git backend.
+struct GitVcs;
+
+impl Vcs for GitVcs {
+    type Versions = GitVersions;
+
+    fn fetch<'io, 'sess: 'io, 'ctx: 'sess>(
+        &self,
+        io: &'io SessionIo<'sess, 'ctx>,
+        name: &str,
+        url: &str,
+        depth: FetchDepth,
+    ) -> Box<Future<Item=(), Error=Error> + 'io> {
+        Box::new(io.git_database(name, url, depth).map(|_| ()))
+    }
+
+    fn list_revs<'io, 'sess: 'io, 'ctx: 'sess>(
+        &self,
+        io: &'io SessionIo<'sess, 'ctx>,
+        name: &str,
+        url: &str,
+    ) -> Box<Future<Item=Vec<String>, Error=Error> + 'io> {
+        Box::new(
+            io.git_database(name, url, FetchDepth::Full)
+                .and_then(|git| git.list_revs())
+        )
+    }
+
+    fn list_versions<'io, 'sess: 'io, 'ctx: 'sess>(
+        &self,
+        io: &'io SessionIo<'sess, 'ctx>,
+        name: &str,
+        url: &str,
+    ) -> Box<Future<Item=GitVersions, Error=Error> + 'io> {
+        let db_dir = io.git_db_dir(name, url);
+        Box::new(
+            io.git_database(name, url, FetchDepth::Full)
+                .and_then(move |git| io.git_versions(git, db_dir))
+        )
+    }
+
+    fn checkout_revision<'io, 'sess: 'io, 'ctx: 'sess>(
+        &self,
+        io: &'io SessionIo<'sess, 'ctx>,
+        name: &str,
+        path: &'ctx Path,
+        url: &str,
+        revision: &str,
+        submodules: bool,
+    ) -> Box<Future<Item=&'ctx Path, Error=Error> + 'io> {
+        let name = name.to_string();
+        let url = url.to_string();
+        let revision = revision.to_string();
+        Box::new(
+            io.git_database(&name, &url, FetchDepth::Revision(&revision))
+                .and_then(move |_| io.checkout_git(&name, path, &url, &revision, submodules))
+        )
+    }
+}
+
+/// The mercurial backend.
+struct HgVcs;
+
+impl Vcs for HgVcs {
+    type Versions = HgVersions;
+
+    fn fetch<'io, 'sess: 'io, 'ctx: 'sess>(
+        &self,
+        io: &'io SessionIo<'sess, 'ctx>,
+        name: &str,
+        url: &str,
+        _depth: FetchDepth,
+    ) -> Box<Future<Item=(), Error=Error> + 'io> {
+        // Mercurial has no notion of a shallow fetch, so `depth` is ignored;
+        // `hg_database` always does a full clone/pull (no pull when frozen).
+        Box::new(future::result(io.hg_database(name, url).map(|_| ())))
+    }
+
+    fn list_revs<'io, 'sess: 'io, 'ctx: 'sess>(
+        &self,
+        io: &'io SessionIo<'sess, 'ctx>,
+        name: &str,
+        url: &str,
+    ) -> Box<Future<Item=Vec<String>, Error=Error> + 'io> {
+        Box::new(future::result(
+            io.hg_database(name, url)
+                .and_then(|db_dir| SessionIo::hg_revs(&db_dir))
+        ))
+    }
+
+    fn list_versions<'io, 'sess: 'io, 'ctx: 'sess>(
+        &self,
+        io: &'io SessionIo<'sess, 'ctx>,
+        name: &str,
+        url: &str,
+    ) -> Box<Future<Item=HgVersions, Error=Error> + 'io> {
+        Box::new(future::result(
+            io.hg_database(name, url)
+                .and_then(|db_dir| io.hg_versions(&db_dir))
+        ))
+    }
+
+    fn checkout_revision<'io, 'sess: 'io, 'ctx: 'sess>(
+        &self,
+        io: &'io SessionIo<'sess, 'ctx>,
+        name: &str,
+        path: &'ctx Path,
+        url: &str,
+        revision: &str,
+        submodules: bool,
+    ) -> Box<Future<Item=&'ctx Path, Error=Error> + 'io> {
+        io.checkout_hg(name, path, url, revision, submodules)
     }
 }
 
@@ -428,6 +1471,9 @@ pub struct DependencyEntry {
     revision: Option<String>,
     /// The picked version.
     version: Option<semver::Version>,
+    /// Whether git submodules should be checked out recursively alongside
+    /// this dependency.
+    submodules: bool,
 }
 
 /// Where a dependency may be obtained from.
@@ -440,6 +1486,8 @@ pub enum DependencySource {
     Path(PathBuf),
     /// The dependency is available at a git url.
     Git(String),
+    /// The dependency is available at a mercurial url.
+    Hg(String),
 }
 
 /// A table of internalized dependencies.
@@ -486,11 +1534,28 @@ pub enum DependencyVersions {
     Registry(RegistryVersions),
     /// Git dependency versions.
     Git(GitVersions),
+    /// Mercurial dependency versions.
+    Hg(HgVersions),
 }
 
 /// All available versions of a registry dependency.
 #[derive(Clone, Debug)]
-pub struct RegistryVersions;
+pub struct RegistryVersions {
+    /// The versions available for this dependency, newest one first, along
+    /// with the coordinates needed to download each of them.
+    pub versions: Vec<(semver::Version, RegistryVersion)>,
+}
+
+/// The coordinates needed to download one specific version of a registry
+/// dependency.
+#[derive(Clone, Debug)]
+pub struct RegistryVersion {
+    /// The URL to download the package's archive from.
+    pub url: String,
+    /// The BLAKE2 checksum of the archive, used to name its checkout
+    /// directory and thus deduplicate identical downloads.
+    pub checksum: String,
+}
 
 /// All available versions a git dependency has.
 #[derive(Clone, Debug)]
@@ -506,6 +1571,46 @@ pub struct GitVersions {
     pub revs: Vec<String>,
 }
 
+/// All available versions a mercurial dependency has.
+///
+/// Mirrors `GitVersions`, but is kept as its own type rather than an alias
+/// since the two backends derive their revisions and tags in unrelated ways
+/// and may grow backend-specific fields later.
+#[derive(Clone, Debug)]
+pub struct HgVersions {
+    /// The versions available for this dependency. This is basically a sorted
+    /// list of tags of the form `v<semver>`.
+    pub versions: Vec<(semver::Version, String)>,
+    /// The named references available for this dependency. This is a mixture
+    /// of branch names and tags, where the tags take precedence.
+    pub refs: HashMap<String, String>,
+    /// The revisions available for this dependency, newest one first.
+    pub revs: Vec<String>,
+}
+
+/// Name of the file, inside each git database, that records when it was
+/// last fetched and what the root manifest looked like at the time.
+const FETCH_FINGERPRINT_FILE: &'static str = ".bender-fetch";
+
+/// Minimum time, in seconds, between two fetches of a git database whose
+/// manifest hasn't changed.
+const FETCH_STALENESS_SECS: u64 = 10 * 60;
+
+/// How much history to pull into a git database.
+///
+/// Walking the full history of a large IP repository just to check out one
+/// pinned commit is wasteful. This lets callers that already know the exact
+/// revision they need ask for a minimal, shallow fetch instead of the full
+/// history that version discovery requires.
+#[derive(Copy, Clone, Debug)]
+enum FetchDepth<'a> {
+    /// Fetch only the single commit at the given revision.
+    Revision(&'a str),
+    /// Fetch the full history, e.g. because semver resolution needs to see
+    /// every tag and branch.
+    Full,
+}
+
 /// A constraint on a dependency.
 #[derive(Clone, Debug)]
 pub enum DependencyConstraint {
@@ -525,10 +1630,12 @@ impl<'a> From<&'a config::Dependency> for DependencyConstraint {
                 DependencyConstraint::Path
             }
             config::Dependency::Version(ref v) |
-            config::Dependency::GitVersion(_, ref v) => {
+            config::Dependency::GitVersion(_, ref v, _) |
+            config::Dependency::HgVersion(_, ref v) => {
                 DependencyConstraint::Version(v.clone())
             }
-            config::Dependency::GitRevision(_, ref r) => {
+            config::Dependency::GitRevision(_, ref r, _) |
+            config::Dependency::HgRevision(_, ref r) => {
                 DependencyConstraint::Revision(r.clone())
             }
         }